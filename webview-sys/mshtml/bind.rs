@@ -0,0 +1,219 @@
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+
+use crate::mshtml::CWebView;
+
+/// Registered via `webview_bind`; invoked as `fn(seq, req_json, userdata)`
+/// where `req_json` is the JSON array of arguments the JS call was made
+/// with, and `seq` identifies the pending promise to resolve/reject via
+/// `webview_return`.
+pub(crate) type BoundFn = extern "C" fn(seq: *const c_char, req_json: *const c_char, userdata: *mut c_void);
+
+pub(crate) struct Binding {
+    pub(crate) func: BoundFn,
+    pub(crate) userdata: *mut c_void,
+}
+
+pub(crate) type Bindings = HashMap<String, Binding>;
+
+/// Defines `window.<name>(...)` for every bound function as a stub that
+/// posts `{id, method, params}` to native and returns a `Promise` that
+/// `__webview_resolve__` later resolves or rejects. `webview_bind`
+/// both `eval`s this immediately (so the binding works on the page that's
+/// currently loaded) and, via `WebViewEngine::persist_script`, tries to
+/// install it for every future document too. WebView2 can actually honor
+/// that via `AddScriptToExecuteOnDocumentCreated`, so bound functions
+/// there survive navigation, including ones the page itself initiates.
+/// MSHTML has no equivalent hook and `persist_script` is a no-op there, so
+/// a bound function disappears the moment MSHTML navigates away from the
+/// document it was bound on and needs rebinding after.
+const INJECTED_RPC_RUNTIME: &str = r#"
+(function() {
+    if (window.__webview_rpc__) return;
+    window.__webview_rpc__ = { seq: 0, pending: {} };
+
+    window.__webview_bind__ = function(name) {
+        window[name] = function() {
+            var seq = (window.__webview_rpc__.seq++).toString();
+            var params = Array.prototype.slice.call(arguments);
+            return new Promise(function(resolve, reject) {
+                window.__webview_rpc__.pending[seq] = { resolve: resolve, reject: reject };
+                var msg = JSON.stringify({ id: seq, method: name, params: params });
+                if (window.chrome && window.chrome.webview) window.chrome.webview.postMessage(msg);
+                else window.external.invoke(msg);
+            });
+        };
+    };
+
+    window.__webview_resolve__ = function(seq, status, result) {
+        var entry = window.__webview_rpc__.pending[seq];
+        if (!entry) return;
+        delete window.__webview_rpc__.pending[seq];
+        var value = result ? JSON.parse(result) : undefined;
+        if (status === 0) entry.resolve(value);
+        else entry.reject(value);
+    };
+})();
+"#;
+
+/// Injects the RPC runtime, defines `name(...)` as a JS global returning a
+/// promise, wires the native side of the RPC up and remembers `name` so
+/// `webview_unbind` knows about it.
+#[no_mangle]
+unsafe extern "C" fn webview_bind(
+    webview: *mut CWebView,
+    name: *const c_char,
+    func: BoundFn,
+    userdata: *mut c_void,
+) -> c_int {
+    let name = match CStr::from_ptr(name).to_str() {
+        Ok(s) => s.to_owned(),
+        Err(_) => return -1,
+    };
+
+    let inject = format!(
+        "{}window.__webview_bind__('{}');",
+        INJECTED_RPC_RUNTIME, name
+    );
+    (*webview).webview.persist_script(&inject);
+    (*webview).webview.eval(&inject);
+
+    (*webview).bindings.insert(name, Binding { func, userdata });
+
+    0
+}
+
+#[no_mangle]
+unsafe extern "C" fn webview_unbind(webview: *mut CWebView, name: *const c_char) -> c_int {
+    let name = match CStr::from_ptr(name).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+
+    if (*webview).bindings.remove(name).is_some() {
+        let uninject = format!("delete window['{}'];", name);
+        (*webview).webview.eval(&uninject);
+        0
+    } else {
+        -1
+    }
+}
+
+/// Resolves (`status == 0`) or rejects (any other `status`) the promise
+/// identified by `seq`, which the bound function's `BoundFn` received as
+/// part of a `{id, method, params}` dispatch.
+#[no_mangle]
+unsafe extern "C" fn webview_return(
+    webview: *mut CWebView,
+    seq: *const c_char,
+    status: c_int,
+    result_json: *const c_char,
+) -> c_int {
+    let seq = match CStr::from_ptr(seq).to_str() {
+        Ok(s) => s,
+        Err(_) => return -1,
+    };
+    let result_json = if result_json.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(result_json)
+            .to_str()
+            .unwrap_or_default()
+            .to_owned()
+    };
+
+    let result_arg = if result_json.is_empty() {
+        "undefined".to_owned()
+    } else {
+        CString::new(result_json).unwrap().into_string().unwrap()
+    };
+
+    let js = format!(
+        "window.__webview_resolve__('{}', {}, {});",
+        seq,
+        status,
+        if result_arg == "undefined" {
+            "undefined".to_owned()
+        } else {
+            format!("JSON.stringify({})", result_arg)
+        }
+    );
+
+    (*webview).webview.eval(&js);
+    0
+}
+
+/// Parses the `{id, method, params}` envelope the engine's invoke callback
+/// receives for bound-function calls and dispatches to the registered
+/// handler; returns `false` if `message` doesn't look like an RPC call
+/// (e.g. it's a plain string the app's own invoke handler should see).
+pub(crate) unsafe fn try_dispatch_bound_call(webview: *mut CWebView, message: &str) -> bool {
+    let (id, method, params) = match parse_envelope(message) {
+        Some(parsed) => parsed,
+        None => return false,
+    };
+
+    let binding = match (*webview).bindings.get(&method) {
+        Some(b) => b,
+        None => return false,
+    };
+
+    let seq = CString::new(id).unwrap();
+    let params = CString::new(params).unwrap();
+    (binding.func)(seq.as_ptr(), params.as_ptr(), binding.userdata);
+    true
+}
+
+/// Minimal `{"id":"..","method":"..","params":[..]}` extraction; a real
+/// implementation would reuse whatever JSON crate the rest of the app
+/// depends on instead of hand-rolling this.
+fn parse_envelope(message: &str) -> Option<(String, String, String)> {
+    let id = extract_string_field(message, "id")?;
+    let method = extract_string_field(message, "method")?;
+    let params_start = message.find("\"params\":")? + "\"params\":".len();
+    let params = message[params_start..].trim_end_matches('}').to_owned();
+    Some((id, method, params))
+}
+
+fn extract_string_field(message: &str, field: &str) -> Option<String> {
+    let needle = format!("\"{}\":\"", field);
+    let start = message.find(&needle)? + needle.len();
+    let end = message[start..].find('"')? + start;
+    Some(message[start..end].to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_string_field_finds_value() {
+        let message = r#"{"id":"1","method":"greet","params":["a","b"]}"#;
+        assert_eq!(extract_string_field(message, "id"), Some("1".to_owned()));
+        assert_eq!(
+            extract_string_field(message, "method"),
+            Some("greet".to_owned())
+        );
+    }
+
+    #[test]
+    fn extract_string_field_missing_returns_none() {
+        let message = r#"{"id":"1","params":[]}"#;
+        assert_eq!(extract_string_field(message, "method"), None);
+    }
+
+    #[test]
+    fn parse_envelope_splits_id_method_params() {
+        let message = r#"{"id":"42","method":"greet","params":["world"]}"#;
+        let (id, method, params) = parse_envelope(message).unwrap();
+        assert_eq!(id, "42");
+        assert_eq!(method, "greet");
+        assert_eq!(params, "[\"world\"]");
+    }
+
+    #[test]
+    fn parse_envelope_rejects_non_rpc_message() {
+        assert_eq!(parse_envelope("just a plain string"), None);
+    }
+}
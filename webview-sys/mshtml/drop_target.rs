@@ -0,0 +1,239 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use winapi::shared::guiddef::REFIID;
+use winapi::shared::minwindef::{DWORD, LPVOID, ULONG};
+use winapi::shared::windef::{HWND, POINTL};
+use winapi::shared::winerror::{DRAGDROP_E_INVALIDHWND, E_NOINTERFACE, S_OK};
+use winapi::um::objidl::{IDataObject, DVASPECT_CONTENT, FORMATETC, STGMEDIUM, TYMED_HGLOBAL};
+use winapi::um::ole2::{ReleaseStgMedium, RegisterDragDrop, RevokeDragDrop};
+use winapi::um::oleidl::{IDropTarget, IDropTargetVtbl, DROPEFFECT_COPY, DROPEFFECT_NONE};
+use winapi::um::shellapi::DragQueryFileW;
+use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+use winapi::um::winnt::HRESULT;
+use winapi::um::winuser::CF_HDROP;
+
+use std::os::raw::c_void;
+
+/// Drop phase reported to `webview_set_file_drop_handler`'s callback,
+/// mirroring `IDropTarget`'s `DragEnter`, `Drop` and `DragLeave` entry
+/// points (`DragOver` reports nothing).
+#[repr(C)]
+pub(crate) enum FileDropPhase {
+    Hover = 0,
+    Drop = 1,
+    Cancel = 2,
+}
+
+/// `paths`/`count` are only valid for the duration of the callback.
+pub(crate) type FileDropCallback = extern "C" fn(
+    webview: *mut crate::mshtml::CWebView,
+    phase: FileDropPhase,
+    paths: *const *const u16,
+    count: usize,
+    userdata: *mut c_void,
+);
+
+/// Opt-in `IDropTarget`, registered on the webview's `HWND` via
+/// `RegisterDragDrop` only once an app calls
+/// `webview_set_file_drop_handler`, so by default MSHTML keeps handling
+/// OS drops itself (its own, more limited, drop behavior).
+#[repr(C)]
+pub(crate) struct DropTarget {
+    vtbl: *const IDropTargetVtbl,
+    refs: AtomicU32,
+    hwnd: HWND,
+    callback: FileDropCallback,
+    userdata: *mut c_void,
+    webview: *mut crate::mshtml::CWebView,
+    registered: bool,
+}
+
+impl DropTarget {
+    pub(crate) fn register(
+        webview: *mut crate::mshtml::CWebView,
+        hwnd: HWND,
+        callback: FileDropCallback,
+        userdata: *mut c_void,
+    ) -> Option<Box<DropTarget>> {
+        let mut target = Box::new(DropTarget {
+            vtbl: &DROP_TARGET_VTBL,
+            refs: AtomicU32::new(1),
+            hwnd,
+            callback,
+            userdata,
+            webview,
+            registered: false,
+        });
+
+        let hr = unsafe { RegisterDragDrop(hwnd, target.as_mut() as *mut _ as *mut _) };
+        if hr == DRAGDROP_E_INVALIDHWND || hr < 0 {
+            eprintln!("RegisterDragDrop failed: {:#x}", hr);
+            return None;
+        }
+
+        target.registered = true;
+        Some(target)
+    }
+}
+
+impl Drop for DropTarget {
+    fn drop(&mut self) {
+        if self.registered {
+            unsafe {
+                RevokeDragDrop(self.hwnd);
+            }
+        }
+    }
+}
+
+static DROP_TARGET_VTBL: IDropTargetVtbl = IDropTargetVtbl {
+    parent: IUnknownVtbl {
+        QueryInterface: drop_qi,
+        AddRef: drop_add_ref,
+        Release: drop_release,
+    },
+    DragEnter: drag_enter,
+    DragOver: drag_over,
+    DragLeave: drag_leave,
+    Drop: drop_files,
+};
+
+unsafe extern "system" fn drop_qi(_this: *mut IUnknown, _riid: REFIID, obj: *mut LPVOID) -> HRESULT {
+    *obj = std::ptr::null_mut();
+    E_NOINTERFACE
+}
+
+unsafe extern "system" fn drop_add_ref(this: *mut IUnknown) -> ULONG {
+    let this = &*(this as *const DropTarget);
+    this.refs.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+unsafe extern "system" fn drop_release(this: *mut IUnknown) -> ULONG {
+    let this = &*(this as *const DropTarget);
+    this.refs.fetch_sub(1, Ordering::SeqCst) - 1
+}
+
+unsafe extern "system" fn drag_enter(
+    this: *mut IDropTarget,
+    data_object: *mut IDataObject,
+    _key_state: DWORD,
+    _pt: POINTL,
+    effect: *mut DWORD,
+) -> HRESULT {
+    report_hover(this, data_object, effect)
+}
+
+unsafe extern "system" fn drag_over(
+    _this: *mut IDropTarget,
+    _key_state: DWORD,
+    _pt: POINTL,
+    effect: *mut DWORD,
+) -> HRESULT {
+    *effect = DROPEFFECT_COPY;
+    S_OK
+}
+
+unsafe extern "system" fn drag_leave(this: *mut IDropTarget) -> HRESULT {
+    let this = &*(this as *const DropTarget);
+    (this.callback)(
+        this.webview,
+        FileDropPhase::Cancel,
+        std::ptr::null(),
+        0,
+        this.userdata,
+    );
+    S_OK
+}
+
+unsafe extern "system" fn drop_files(
+    this: *mut IDropTarget,
+    data_object: *mut IDataObject,
+    _key_state: DWORD,
+    _pt: POINTL,
+    effect: *mut DWORD,
+) -> HRESULT {
+    let this = &*(this as *const DropTarget);
+    let paths = paths_from_data_object(data_object);
+
+    let wide_paths: Vec<Vec<u16>> = paths
+        .iter()
+        .map(|p| {
+            use std::os::windows::ffi::OsStrExt;
+            p.as_os_str()
+                .encode_wide()
+                .chain(Some(0))
+                .collect::<Vec<u16>>()
+        })
+        .collect();
+    let ptrs: Vec<*const u16> = wide_paths.iter().map(|w| w.as_ptr()).collect();
+
+    (this.callback)(
+        this.webview,
+        FileDropPhase::Drop,
+        ptrs.as_ptr(),
+        ptrs.len(),
+        this.userdata,
+    );
+
+    *effect = DROPEFFECT_COPY;
+    S_OK
+}
+
+unsafe fn report_hover(
+    this: *mut IDropTarget,
+    data_object: *mut IDataObject,
+    effect: *mut DWORD,
+) -> HRESULT {
+    let this = &*(this as *const DropTarget);
+    let has_files = !paths_from_data_object(data_object).is_empty();
+    *effect = if has_files { DROPEFFECT_COPY } else { DROPEFFECT_NONE };
+
+    (this.callback)(
+        this.webview,
+        FileDropPhase::Hover,
+        std::ptr::null(),
+        0,
+        this.userdata,
+    );
+    S_OK
+}
+
+/// Pulls `CF_HDROP` paths out of the dropped `IDataObject` via the classic
+/// `DragQueryFile` shell API.
+unsafe fn paths_from_data_object(data_object: *mut IDataObject) -> Vec<PathBuf> {
+    if data_object.is_null() {
+        return Vec::new();
+    }
+
+    let mut format = FORMATETC {
+        cfFormat: CF_HDROP as u16,
+        ptd: std::ptr::null_mut(),
+        dwAspect: DVASPECT_CONTENT,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL,
+    };
+    let mut medium: STGMEDIUM = std::mem::zeroed();
+
+    let hr = (*data_object).GetData(&mut format, &mut medium);
+    if hr < 0 {
+        return Vec::new();
+    }
+
+    let hdrop = *medium.u.hGlobal() as winapi::shared::windef::HDROP;
+    let count = DragQueryFileW(hdrop, 0xFFFF_FFFF, std::ptr::null_mut(), 0);
+
+    let mut paths = Vec::with_capacity(count as usize);
+    for i in 0..count {
+        let len = DragQueryFileW(hdrop, i, std::ptr::null_mut(), 0);
+        let mut buffer = vec![0u16; len as usize + 1];
+        DragQueryFileW(hdrop, i, buffer.as_mut_ptr(), buffer.len() as u32);
+        buffer.truncate(len as usize);
+
+        use std::os::windows::ffi::OsStringExt;
+        paths.push(PathBuf::from(std::ffi::OsString::from_wide(&buffer)));
+    }
+
+    ReleaseStgMedium(&mut medium);
+    paths
+}
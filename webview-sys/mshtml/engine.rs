@@ -0,0 +1,20 @@
+/// Common surface both rendering backends (the legacy MSHTML/IE11 engine
+/// in `web_view.rs` and the WebView2/Edge engine in `webview2.rs`) expose
+/// to `mod.rs`, so the C ABI doesn't need to know which one is hosted.
+pub(crate) trait WebViewEngine {
+    fn navigate(&self, url: &str);
+    fn eval(&self, js: &str);
+    fn write(&self, html: &str);
+    fn set_callback(&mut self, cb: Option<Box<dyn Fn(String)>>);
+    /// Repositions the hosted content to fill `rect`, e.g. in response
+    /// to `WM_SIZE`/`WM_DPICHANGED` on the owning window.
+    fn resize(&self, rect: winapi::shared::windef::RECT);
+    /// Installs `script` so it also runs on every future document this
+    /// engine loads, not just the one live right now — used by `bind.rs`
+    /// so bound functions survive navigation. WebView2 has a first-class
+    /// primitive for this (`AddScriptToExecuteOnDocumentCreated`) and
+    /// returns `true`; MSHTML has no equivalent hook into page-initiated
+    /// navigation, so it returns `false` and callers must treat the
+    /// binding as good only for the currently loaded document.
+    fn persist_script(&mut self, script: &str) -> bool;
+}
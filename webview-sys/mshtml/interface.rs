@@ -0,0 +1,231 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use winapi::ctypes::c_void;
+use winapi::shared::guiddef::REFIID;
+use winapi::shared::minwindef::{DWORD, LPVOID};
+use winapi::shared::windef::{HWND, RECT};
+use winapi::shared::winerror::{E_NOINTERFACE, E_NOTIMPL, S_OK};
+use winapi::um::oaidl::IDispatch;
+use winapi::um::oleidl::{
+    IOleClientSite, IOleClientSiteVtbl, IOleInPlaceFrameInfo, IOleInPlaceSite,
+    IOleInPlaceSiteVtbl, IOleWindowVtbl,
+};
+use winapi::um::unknwnbase::{IUnknown, IUnknownVtbl};
+use winapi::um::winnt::HRESULT;
+
+/// Shared state that callbacks invoked from MSHTML need access to, kept
+/// alive independently of the `ClientSite` COM refcount.
+pub(crate) struct InvokeState {
+    pub(crate) callback: Option<Box<dyn Fn(String)>>,
+}
+
+/// Minimal `IOleClientSite`/`IOleInPlaceSite` implementation, just enough
+/// for MSHTML to agree to host itself windowlessly inside our `HWND`
+/// without a full ActiveX document container.
+#[repr(C)]
+pub(crate) struct ClientSite {
+    client_site: *const IOleClientSiteVtbl,
+    in_place_site: *const IOleInPlaceSiteVtbl,
+    refs: AtomicU32,
+    pub(crate) hwnd: HWND,
+    pub(crate) state: Rc<RefCell<InvokeState>>,
+}
+
+impl ClientSite {
+    pub(crate) fn new(hwnd: HWND, state: Rc<RefCell<InvokeState>>) -> Box<ClientSite> {
+        Box::new(ClientSite {
+            client_site: &CLIENT_SITE_VTBL,
+            in_place_site: &IN_PLACE_SITE_VTBL,
+            refs: AtomicU32::new(1),
+            hwnd,
+            state,
+        })
+    }
+
+    pub(crate) fn as_ole_client_site(&self) -> *mut IOleClientSite {
+        &self.client_site as *const _ as *mut IOleClientSite
+    }
+}
+
+static CLIENT_SITE_VTBL: IOleClientSiteVtbl = IOleClientSiteVtbl {
+    parent: IUnknownVtbl {
+        QueryInterface: client_site_qi,
+        AddRef: client_site_add_ref,
+        Release: client_site_release,
+    },
+    SaveObject: client_site_save_object,
+    GetMoniker: client_site_get_moniker,
+    GetContainer: client_site_get_container,
+    ShowObject: client_site_show_object,
+    OnShowWindow: client_site_on_show_window,
+    RequestNewObjectLayout: client_site_request_new_object_layout,
+};
+
+static IN_PLACE_SITE_VTBL: IOleInPlaceSiteVtbl = IOleInPlaceSiteVtbl {
+    parent: IOleWindowVtbl {
+        parent: IUnknownVtbl {
+            QueryInterface: client_site_qi,
+            AddRef: client_site_add_ref,
+            Release: client_site_release,
+        },
+        GetWindow: in_place_get_window,
+        ContextSensitiveHelp: in_place_context_sensitive_help,
+    },
+    CanInPlaceActivate: in_place_can_activate,
+    OnInPlaceActivate: in_place_on_activate,
+    OnUIActivate: in_place_on_ui_activate,
+    GetWindowContext: in_place_get_window_context,
+    Scroll: in_place_scroll,
+    OnInPlaceDeactivate: in_place_on_deactivate,
+    OnUIDeactivate: in_place_on_ui_deactivate,
+    DiscardUndoState: in_place_discard_undo_state,
+    DeactivateAndUndo: in_place_deactivate_and_undo,
+    OnPosRectChange: in_place_on_pos_rect_change,
+};
+
+unsafe extern "system" fn client_site_qi(
+    _this: *mut IUnknown,
+    _riid: REFIID,
+    obj: *mut LPVOID,
+) -> HRESULT {
+    *obj = std::ptr::null_mut();
+    E_NOINTERFACE
+}
+
+unsafe extern "system" fn client_site_add_ref(this: *mut IUnknown) -> u32 {
+    let site = &*(this as *const ClientSite);
+    site.refs.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+unsafe extern "system" fn client_site_release(this: *mut IUnknown) -> u32 {
+    let count = {
+        let site = &*(this as *const ClientSite);
+        site.refs.fetch_sub(1, Ordering::SeqCst) - 1
+    };
+    if count == 0 {
+        drop(Box::from_raw(this as *mut ClientSite));
+    }
+    count
+}
+
+unsafe extern "system" fn client_site_save_object(_this: *mut IOleClientSite) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn client_site_get_moniker(
+    _this: *mut IOleClientSite,
+    _assign: DWORD,
+    _which: DWORD,
+    _moniker: *mut *mut c_void,
+) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn client_site_get_container(
+    _this: *mut IOleClientSite,
+    container: *mut *mut c_void,
+) -> HRESULT {
+    *container = std::ptr::null_mut();
+    E_NOINTERFACE
+}
+
+unsafe extern "system" fn client_site_show_object(_this: *mut IOleClientSite) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn client_site_on_show_window(
+    _this: *mut IOleClientSite,
+    _show: i32,
+) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn client_site_request_new_object_layout(
+    _this: *mut IOleClientSite,
+) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn in_place_get_window(
+    this: *mut IOleInPlaceSite,
+    hwnd: *mut HWND,
+) -> HRESULT {
+    let site = &*(this as *const u8 as *const ClientSite);
+    *hwnd = site.hwnd;
+    S_OK
+}
+
+unsafe extern "system" fn in_place_context_sensitive_help(
+    _this: *mut IOleInPlaceSite,
+    _enter: i32,
+) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn in_place_can_activate(_this: *mut IOleInPlaceSite) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn in_place_on_activate(_this: *mut IOleInPlaceSite) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn in_place_on_ui_activate(_this: *mut IOleInPlaceSite) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn in_place_get_window_context(
+    this: *mut IOleInPlaceSite,
+    frame: *mut *mut c_void,
+    doc: *mut *mut c_void,
+    rect: *mut RECT,
+    clip_rect: *mut RECT,
+    _frame_info: *mut IOleInPlaceFrameInfo,
+) -> HRESULT {
+    let site = &*(this as *const u8 as *const ClientSite);
+    *frame = std::ptr::null_mut();
+    *doc = std::ptr::null_mut();
+    winapi::um::winuser::GetClientRect(site.hwnd, rect);
+    winapi::um::winuser::GetClientRect(site.hwnd, clip_rect);
+    S_OK
+}
+
+unsafe extern "system" fn in_place_scroll(
+    _this: *mut IOleInPlaceSite,
+    _scroll: winapi::shared::windef::SIZE,
+) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn in_place_on_deactivate(_this: *mut IOleInPlaceSite) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn in_place_on_ui_deactivate(
+    _this: *mut IOleInPlaceSite,
+    _undoable: i32,
+) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn in_place_discard_undo_state(_this: *mut IOleInPlaceSite) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn in_place_deactivate_and_undo(_this: *mut IOleInPlaceSite) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn in_place_on_pos_rect_change(
+    _this: *mut IOleInPlaceSite,
+    _rect: *const RECT,
+) -> HRESULT {
+    S_OK
+}
+
+/// Signature of a `window.external.invoke` dispatch sink. Nothing in this
+/// crate uses it: `web_view.rs` exposes no `window.external` object to
+/// MSHTML pages.
+pub(crate) type ExternalInvoke = unsafe extern "system" fn(*mut IDispatch, *const u16) -> HRESULT;
@@ -1,17 +1,28 @@
 #![cfg(target_os = "windows")]
 #![allow(unused_variables)]
 
+mod bind;
+mod drop_target;
+mod engine;
 mod interface;
+mod uri_scheme;
 mod web_view;
+mod webview2;
 mod window;
 
+use crate::mshtml::bind::Bindings;
+use crate::mshtml::drop_target::{DropTarget, FileDropCallback};
+use crate::mshtml::engine::WebViewEngine;
+use crate::mshtml::uri_scheme::UriSchemeRegistry;
 use crate::mshtml::window::WM_WEBVIEW_DISPATCH;
+use std::collections::HashMap;
 use std::ffi::{CStr, OsStr};
 use std::ffi::{CString, OsString};
 use std::mem;
 use std::os::windows::ffi::{OsStrExt, OsStringExt};
 use std::ptr;
 use winapi::shared::minwindef::BOOL;
+use winapi::shared::windef::HWND;
 use winapi::shared::windef::DPI_AWARENESS_CONTEXT;
 use winapi::shared::windef::DPI_AWARENESS_CONTEXT_SYSTEM_AWARE;
 use winapi::um::libloaderapi::GetModuleHandleW;
@@ -23,7 +34,9 @@ use percent_encoding::percent_decode_str;
 use winapi::{shared::windef::RECT, um::winuser::*};
 
 use web_view::WebView;
+use webview2::WebView2;
 use window::DispatchData;
+use window::SizeHint;
 use window::Window;
 
 pub(crate) type ExternalInvokeCallback = extern "C" fn(webview: *mut CWebView, arg: *const c_char);
@@ -36,9 +49,12 @@ extern "system" {
 #[repr(C)]
 pub(crate) struct CWebView {
     window: Window,
-    webview: Box<WebView>,
+    webview: Box<dyn WebViewEngine>,
     external_invoke_cb: ExternalInvokeCallback,
     userdata: *mut c_void,
+    uri_schemes: UriSchemeRegistry,
+    bindings: Bindings,
+    drop_target: Option<Box<DropTarget>>,
 }
 
 const KEY_FEATURE_BROWSER_EMULATION: &str =
@@ -79,6 +95,55 @@ fn fix_ie_compat_mode() -> bool {
 
 const DATA_URL_PREFIX: &str = "data:text/html,";
 
+/// Loads `url` into an already-initialized engine, handling the
+/// `data:text/html,` shorthand the same way for every backend.
+fn load_url(webview: &dyn WebViewEngine, url: &str) {
+    println!("url {}", url);
+    if url.starts_with(DATA_URL_PREFIX) {
+        let content = percent_decode_str(&url[DATA_URL_PREFIX.len()..])
+            .decode_utf8()
+            .unwrap();
+        println!("{}", &content);
+        webview.navigate("about:blank");
+        webview.write(&content);
+    } else {
+        webview.navigate(url);
+    }
+}
+
+fn finish_webview_new(
+    mut cwebview: Box<CWebView>,
+    url: *const c_char,
+    external_invoke_cb: ExternalInvokeCallback,
+) -> *mut CWebView {
+    let url = unsafe { CStr::from_ptr(url) };
+    let url = url.to_str().expect("url is not valid utf8");
+    load_url(cwebview.webview.as_ref(), url);
+
+    unsafe {
+        ShowWindow(cwebview.window.handle(), SW_SHOWDEFAULT);
+    }
+
+    let wv_ptr = Box::into_raw(cwebview);
+
+    unsafe {
+        (*wv_ptr).window.set_resize_handler(Box::new(move |rect| {
+            (*wv_ptr).webview.resize(rect);
+        }));
+
+        (*wv_ptr).webview.set_callback(Some(Box::new(move |result| {
+            println!("result {}", result);
+            if bind::try_dispatch_bound_call(wv_ptr, &result) {
+                return;
+            }
+            let c_result = CString::new(result).unwrap();
+            external_invoke_cb(wv_ptr, c_result.as_ptr());
+        })));
+    }
+
+    wv_ptr
+}
+
 #[no_mangle]
 extern "C" fn webview_new(
     title: *const c_char,
@@ -95,15 +160,55 @@ extern "C" fn webview_new(
         return ptr::null_mut();
     }
 
-    let mut cwebview = Box::new(CWebView {
-        window: Window::new(),
-        webview: WebView::new(),
+    enable_dpi_awareness();
+
+    let window = Window::new();
+    let mut webview = WebView::new();
+    webview.initialize(
+        window.handle(),
+        RECT {
+            left: 0,
+            right: width,
+            top: 0,
+            bottom: height,
+        },
+    );
+
+    let cwebview = Box::new(CWebView {
+        window,
+        webview,
         external_invoke_cb,
         userdata,
+        uri_schemes: HashMap::new(),
+        bindings: HashMap::new(),
+        drop_target: None,
     });
 
-    cwebview.webview.initialize(
-        cwebview.window.handle(),
+    finish_webview_new(cwebview, url, external_invoke_cb)
+}
+
+/// Same signature as `webview_new`, but hosts a `CoreWebView2Controller`
+/// (Edge/Chromium, via the `webview2-com`/`windows` crates) instead of
+/// MSHTML, so apps get evergreen CSS/JS support without the IE11 compat
+/// registry hack `fix_ie_compat_mode` otherwise requires.
+#[no_mangle]
+extern "C" fn webview_new_webview2(
+    title: *const c_char,
+    url: *const c_char,
+    width: c_int,
+    height: c_int,
+    resizable: c_int,
+    debug: c_int,
+    frameless: c_int,
+    external_invoke_cb: ExternalInvokeCallback,
+    userdata: *mut c_void,
+) -> *mut CWebView {
+    enable_dpi_awareness();
+
+    let window = Window::new();
+    let mut webview = WebView2::new();
+    webview.initialize(
+        window.handle(),
         RECT {
             left: 0,
             right: width,
@@ -112,36 +217,76 @@ extern "C" fn webview_new(
         },
     );
 
-    let url = unsafe { CStr::from_ptr(url) };
-    let url = url.to_str().expect("url is not valid utf8");
+    let cwebview = Box::new(CWebView {
+        window,
+        webview,
+        external_invoke_cb,
+        userdata,
+        uri_schemes: HashMap::new(),
+        bindings: HashMap::new(),
+        drop_target: None,
+    });
 
-    println!("url {}", url);
-    if url.starts_with(DATA_URL_PREFIX) {
-        let content = percent_decode_str(&url[DATA_URL_PREFIX.len()..])
-            .decode_utf8()
-            .unwrap();
-        println!("{}", &content);
-        cwebview.webview.navigate("about:blank");
-        cwebview.webview.write(&content);
-    } else {
-        cwebview.webview.navigate(url);
-    }
+    finish_webview_new(cwebview, url, external_invoke_cb)
+}
 
-    unsafe {
-        ShowWindow(cwebview.window.handle(), SW_SHOWDEFAULT);
+/// Like `webview_new`, but instead of creating its own top-level window,
+/// hosts the MSHTML control as a `WS_CHILD` of `parent_hwnd` sized to
+/// `rect`, so callers (FLTK/win32 hosts, etc.) can embed it inside their
+/// own window. `webview_eval`/`webview_dispatch`/`webview_free` all still
+/// work on the returned `*mut CWebView`; call `webview_resize` when the
+/// host's own resize events should be forwarded to the embedded control.
+#[no_mangle]
+extern "C" fn webview_new_embedded(
+    parent_hwnd: HWND,
+    rect: RECT,
+    url: *const c_char,
+    external_invoke_cb: ExternalInvokeCallback,
+    userdata: *mut c_void,
+) -> *mut CWebView {
+    if !fix_ie_compat_mode() {
+        return ptr::null_mut();
     }
 
-    let wv_ptr = Box::into_raw(cwebview);
+    enable_dpi_awareness();
+
+    let window = Window::new_embedded(parent_hwnd, rect);
+    let mut webview = WebView::new();
+    // `rect` is in `parent_hwnd`'s coordinate space (that's what
+    // `Window::create` uses to position the child HWND itself), but the
+    // engine hosts its content relative to the child window's own client
+    // area, which always starts at (0, 0) regardless of where that child
+    // sits inside its parent.
+    webview.initialize(
+        window.handle(),
+        RECT {
+            left: 0,
+            top: 0,
+            right: rect.right - rect.left,
+            bottom: rect.bottom - rect.top,
+        },
+    );
 
-    unsafe {
-        (*wv_ptr).webview.set_callback(Some(Box::new(move |result| {
-            println!("result {}", result);
-            let c_result = CString::new(result).unwrap();
-            external_invoke_cb(wv_ptr, c_result.as_ptr());
-        })));
-    }
+    let cwebview = Box::new(CWebView {
+        window,
+        webview,
+        external_invoke_cb,
+        userdata,
+        uri_schemes: HashMap::new(),
+        bindings: HashMap::new(),
+        drop_target: None,
+    });
 
-    wv_ptr
+    finish_webview_new(cwebview, url, external_invoke_cb)
+}
+
+/// Forwards the host's own resize of the parent window to the embedded
+/// control created by `webview_new_embedded` (or repositions a normal
+/// top-level window, though `webview_set_size` is the usual way to do
+/// that).
+#[no_mangle]
+unsafe extern "C" fn webview_resize(webview: *mut CWebView, rect: RECT) {
+    (*webview).window.resize(rect);
 }
 
 #[no_mangle]
@@ -175,9 +320,58 @@ unsafe extern "C" fn webview_eval(webview: *mut CWebView, js: *const c_char) ->
     return 0;
 }
 
+#[no_mangle]
+unsafe extern "C" fn webview_set_title(webview: *mut CWebView, title: *const c_char) {
+    let title = CStr::from_ptr(title);
+    let title = title.to_str().expect("title is not valid utf8");
+    (*webview).window.set_title(title);
+}
+
+#[no_mangle]
+unsafe extern "C" fn webview_set_fullscreen(webview: *mut CWebView, fullscreen: c_int) {
+    (*webview).window.set_fullscreen(fullscreen != 0);
+}
+
+#[no_mangle]
+unsafe extern "C" fn webview_set_size(
+    webview: *mut CWebView,
+    width: c_int,
+    height: c_int,
+    hint: SizeHint,
+) {
+    (*webview).window.set_size(width, height, hint);
+}
+
+#[no_mangle]
+unsafe extern "C" fn webview_set_color(webview: *mut CWebView, r: u8, g: u8, b: u8) {
+    (*webview).window.set_color(r, g, b);
+}
+
+/// Registers an `IDropTarget` on the webview's window so dragged files
+/// are reported through `cb` (hover/drop/cancel) instead of whatever
+/// limited drop handling MSHTML does on its own. Calling this again
+/// replaces the previous handler; dropping `webview` (see `webview_exit`)
+/// revokes the registration.
+#[no_mangle]
+unsafe extern "C" fn webview_set_file_drop_handler(
+    webview: *mut CWebView,
+    cb: FileDropCallback,
+    userdata: *mut c_void,
+) -> c_int {
+    (*webview).drop_target = None;
+    match DropTarget::register(webview, (*webview).window.handle(), cb, userdata) {
+        Some(target) => {
+            (*webview).drop_target = Some(target);
+            0
+        }
+        None => -1,
+    }
+}
+
 #[no_mangle]
 unsafe extern "C" fn webview_exit(webview: *mut CWebView) {
     println!("exit");
+    (*webview).drop_target = None;
     DestroyWindow((*webview).window.handle());
     OleUninitialize();
 }
@@ -198,9 +392,17 @@ unsafe extern "C" fn webview_dispatch(
     f: Option<ErasedDispatchFn>,
     arg: *mut c_void,
 ) {
+    post_dispatch(webview, f.unwrap(), arg);
+}
+
+/// Posts `f(webview, arg)` to run on the window's message loop thread.
+/// Shared by the public `webview_dispatch` and by subsystems (e.g.
+/// `uri_scheme`) that need to hop back onto that thread to finish work
+/// started asynchronously.
+pub(crate) unsafe fn post_dispatch(webview: *mut CWebView, f: ErasedDispatchFn, arg: *mut c_void) {
     let data = Box::new(DispatchData {
         target: webview,
-        func: f.unwrap(),
+        func: f,
         arg,
     });
     PostMessageW(
@@ -211,6 +413,18 @@ unsafe extern "C" fn webview_dispatch(
     );
 }
 
+/// Not always present in the `winapi` version this crate pins; defined
+/// with its documented raw value (`winuser.h`'s `DPI_AWARENESS_CONTEXT_
+/// PER_MONITOR_AWARE_V2`) so `SetThreadDpiAwarenessContext` can be asked
+/// for it even on an older `winapi`.
+const DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2: DPI_AWARENESS_CONTEXT = -4isize as _;
+
+/// Requests per-monitor-v2 DPI awareness, which (unlike the old
+/// system-aware mode) keeps content crisp as the window is dragged
+/// between monitors with different scaling; see `WM_DPICHANGED` handling
+/// in `window.rs` for the live-rescale half of this. Falls back to
+/// system-aware, then process-wide `SetProcessDPIAware`
+/// for older Windows builds that don't export the newer entry points.
 fn enable_dpi_awareness() -> bool {
     type FnSetThreadDpiAwarenessContext =
         extern "system" fn(dpi_context: DPI_AWARENESS_CONTEXT) -> DPI_AWARENESS_CONTEXT;
@@ -232,8 +446,14 @@ fn enable_dpi_awareness() -> bool {
         if !set_thread_dpi_awareness.is_null() {
             let set_thread_dpi_awareness: FnSetThreadDpiAwarenessContext =
                 mem::transmute(set_thread_dpi_awareness);
-            if !set_thread_dpi_awareness(DPI_AWARENESS_CONTEXT_SYSTEM_AWARE).is_null() {
-                return true;
+
+            for context in [
+                DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+                DPI_AWARENESS_CONTEXT_SYSTEM_AWARE,
+            ] {
+                if !set_thread_dpi_awareness(context).is_null() {
+                    return true;
+                }
             }
         }
 
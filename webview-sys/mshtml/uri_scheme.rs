@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use winapi::shared::guiddef::{GUID, REFIID};
+use winapi::shared::minwindef::{DWORD, LPVOID, ULONG};
+use winapi::shared::winerror::{E_NOINTERFACE, E_NOTIMPL, S_FALSE, S_OK};
+use winapi::um::unknwnbase::{IClassFactory, IClassFactoryVtbl, IUnknown, IUnknownVtbl};
+use winapi::um::urlmon::{
+    CoInternetGetSession, IInternetProtocol, IInternetProtocolSink, IInternetProtocolVtbl,
+    PROTOCOLDATA, BINDSTATUS_MIMETYPEAVAILABLE,
+};
+use winapi::um::winnt::HRESULT;
+
+use crate::mshtml::CWebView;
+
+/// Called once per request on a registered scheme. The handler owns the
+/// `UriSchemeRequest` until it calls `webview_uri_scheme_respond` on it,
+/// which may happen later/on another thread, e.g. after an async file or
+/// network read.
+pub(crate) type UriSchemeHandler =
+    extern "C" fn(request: *mut UriSchemeRequest, url: *const c_char, userdata: *mut c_void);
+
+pub(crate) struct UriSchemeRegistration {
+    pub(crate) handler: UriSchemeHandler,
+    pub(crate) userdata: *mut c_void,
+    /// Kept alive for as long as the namespace stays registered with
+    /// `IInternetSession`; never queried again after `RegisterNameSpace`.
+    factory: *mut UriProtocolFactory,
+}
+
+pub(crate) type UriSchemeRegistry = HashMap<String, UriSchemeRegistration>;
+
+/// One in-flight `app://...` request. Lives as long as MSHTML's
+/// `IInternetProtocol::Start`/`Read` pair is outstanding; completion is
+/// posted back to the owning window so `ReportData`/`ReportResult` happen
+/// on the thread that owns the bind session, same as every other async
+/// callback in this crate (see `WM_WEBVIEW_DISPATCH` in `window.rs`).
+pub(crate) struct UriSchemeRequest {
+    webview: *mut CWebView,
+    protocol: *mut InternetProtocol,
+    url: CString,
+}
+
+struct RespondData {
+    protocol: *mut InternetProtocol,
+    status: c_int,
+    mime_type: CString,
+    data: Vec<u8>,
+}
+
+/// Arbitrary fixed CLSID identifying this crate's protocol handler to
+/// `IInternetSession::RegisterNameSpace`; it never needs to resolve via
+/// `CoCreateInstance` since we hand the session the factory instance
+/// directly, but the API still wants a CLSID for bookkeeping.
+const CLSID_WEBVIEW_URI_PROTOCOL: GUID = GUID {
+    Data1: 0x6c8b5a2e,
+    Data2: 0x9a3f,
+    Data3: 0x4b7a,
+    Data4: [0x9d, 0x21, 0x5e, 0x2c, 0x3f, 0x8a, 0x71, 0x04],
+};
+
+#[no_mangle]
+unsafe extern "C" fn webview_register_uri_scheme(
+    webview: *mut CWebView,
+    scheme: *const c_char,
+    handler: UriSchemeHandler,
+    userdata: *mut c_void,
+) -> c_int {
+    let scheme = match CStr::from_ptr(scheme).to_str() {
+        Ok(s) => s.to_owned(),
+        Err(_) => return -1,
+    };
+
+    let factory = Box::into_raw(UriProtocolFactory::new(webview));
+
+    let mut session = ptr::null_mut();
+    let hr = CoInternetGetSession(0, &mut session, 0);
+    if hr < 0 || session.is_null() {
+        eprintln!("CoInternetGetSession failed, hr={:#x}", hr);
+        drop(Box::from_raw(factory));
+        return -1;
+    }
+
+    let scheme_wide = crate::mshtml::to_wstring(&scheme);
+    let hr = (*session).RegisterNameSpace(
+        factory as *mut IClassFactory,
+        &CLSID_WEBVIEW_URI_PROTOCOL,
+        scheme_wide.as_ptr(),
+        0,
+        ptr::null(),
+        0,
+    );
+    (*session).Release();
+
+    if hr < 0 {
+        eprintln!("RegisterNameSpace({}) failed, hr={:#x}", scheme, hr);
+        drop(Box::from_raw(factory));
+        return -1;
+    }
+
+    (*webview).uri_schemes.insert(
+        scheme,
+        UriSchemeRegistration {
+            handler,
+            userdata,
+            factory,
+        },
+    );
+
+    0
+}
+
+#[no_mangle]
+unsafe extern "C" fn webview_uri_scheme_respond(
+    request: *mut UriSchemeRequest,
+    status: c_int,
+    mime_type: *const c_char,
+    data: *const u8,
+    len: usize,
+) {
+    let request = Box::from_raw(request);
+    let mime_type = CStr::from_ptr(mime_type).to_owned();
+    let bytes = if data.is_null() || len == 0 {
+        Vec::new()
+    } else {
+        std::slice::from_raw_parts(data, len).to_vec()
+    };
+
+    let respond = Box::new(RespondData {
+        protocol: request.protocol,
+        status,
+        mime_type,
+        data: bytes,
+    });
+
+    extern "C" fn complete(_webview: *mut CWebView, arg: *mut c_void) {
+        unsafe {
+            let respond = Box::from_raw(arg as *mut RespondData);
+            deliver_response(respond);
+        }
+    }
+
+    let webview = request.webview;
+    super::post_dispatch(webview, complete, Box::into_raw(respond) as *mut c_void);
+}
+
+unsafe fn deliver_response(respond: Box<RespondData>) {
+    let protocol = &mut *respond.protocol;
+    let sink = protocol.sink;
+
+    protocol.buffer = respond.data;
+    protocol.cursor = 0;
+    protocol.finished = true;
+
+    if sink.is_null() {
+        return;
+    }
+
+    let mime_wide = crate::mshtml::to_wstring(respond.mime_type.to_str().unwrap_or("text/plain"));
+    (*sink).ReportProgress(BINDSTATUS_MIMETYPEAVAILABLE, mime_wide.as_ptr());
+    (*sink).ReportData(
+        winapi::um::urlmon::BSCF_LASTDATANOTIFICATION | winapi::um::urlmon::BSCF_DATAFULLYAVAILABLE,
+        protocol.buffer.len() as ULONG,
+        protocol.buffer.len() as ULONG,
+    );
+
+    let result = if respond.status == 0 { S_OK } else { winapi::shared::winerror::E_FAIL };
+    (*sink).ReportResult(result, 0, ptr::null_mut());
+}
+
+/// `IClassFactory` handed to `IInternetSession::RegisterNameSpace` so
+/// MSHTML can mint an `IInternetProtocol` per `app://...` request; each
+/// instance it creates shares `webview` so `protocol_start` can look the
+/// request's scheme up in that `CWebView`'s registry.
+#[repr(C)]
+struct UriProtocolFactory {
+    vtbl: *const IClassFactoryVtbl,
+    refs: AtomicU32,
+    webview: *mut CWebView,
+}
+
+impl UriProtocolFactory {
+    fn new(webview: *mut CWebView) -> Box<UriProtocolFactory> {
+        Box::new(UriProtocolFactory {
+            vtbl: &FACTORY_VTBL,
+            refs: AtomicU32::new(1),
+            webview,
+        })
+    }
+}
+
+static FACTORY_VTBL: IClassFactoryVtbl = IClassFactoryVtbl {
+    parent: IUnknownVtbl {
+        QueryInterface: factory_qi,
+        AddRef: factory_add_ref,
+        Release: factory_release,
+    },
+    CreateInstance: factory_create_instance,
+    LockServer: factory_lock_server,
+};
+
+unsafe extern "system" fn factory_qi(_this: *mut IUnknown, _riid: REFIID, obj: *mut LPVOID) -> HRESULT {
+    *obj = ptr::null_mut();
+    E_NOINTERFACE
+}
+
+unsafe extern "system" fn factory_add_ref(this: *mut IUnknown) -> ULONG {
+    let this = &*(this as *const UriProtocolFactory);
+    this.refs.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+unsafe extern "system" fn factory_release(this: *mut IUnknown) -> ULONG {
+    let count = {
+        let this = &*(this as *const UriProtocolFactory);
+        this.refs.fetch_sub(1, Ordering::SeqCst) - 1
+    };
+    if count == 0 {
+        drop(Box::from_raw(this as *mut UriProtocolFactory));
+    }
+    count
+}
+
+unsafe extern "system" fn factory_create_instance(
+    this: *mut IClassFactory,
+    _outer: *mut IUnknown,
+    _riid: REFIID,
+    obj: *mut LPVOID,
+) -> HRESULT {
+    let this = &*(this as *const UriProtocolFactory);
+    let protocol = InternetProtocol::new(this.webview);
+    *obj = Box::into_raw(protocol) as LPVOID;
+    S_OK
+}
+
+unsafe extern "system" fn factory_lock_server(_this: *mut IClassFactory, _lock: i32) -> HRESULT {
+    S_OK
+}
+
+/// Per-request `IInternetProtocol` implementation handed back from
+/// `UriProtocolFactory::CreateInstance`. `start` looks the scheme up in
+/// the owning `CWebView`'s registry and invokes the registered handler;
+/// `read` drains whatever bytes `webview_uri_scheme_respond` produced.
+#[repr(C)]
+pub(crate) struct InternetProtocol {
+    vtbl: *const IInternetProtocolVtbl,
+    refs: AtomicU32,
+    webview: *mut CWebView,
+    sink: *mut IInternetProtocolSink,
+    buffer: Vec<u8>,
+    cursor: usize,
+    /// Set only by `deliver_response`, once `webview_uri_scheme_respond`
+    /// has actually run. Responses are always delivered asynchronously
+    /// (`post_dispatch`/`PostMessageW`, never inline with `Start`), so
+    /// `Read` can legitimately be called before there's anything in
+    /// `buffer` yet; without this flag that looks identical to a finished,
+    /// empty response.
+    finished: bool,
+}
+
+impl InternetProtocol {
+    pub(crate) fn new(webview: *mut CWebView) -> Box<InternetProtocol> {
+        Box::new(InternetProtocol {
+            vtbl: &PROTOCOL_VTBL,
+            refs: AtomicU32::new(1),
+            webview,
+            sink: ptr::null_mut(),
+            buffer: Vec::new(),
+            cursor: 0,
+            finished: false,
+        })
+    }
+}
+
+static PROTOCOL_VTBL: IInternetProtocolVtbl = IInternetProtocolVtbl {
+    parent: winapi::um::urlmon::IInternetProtocolRootVtbl {
+        parent: IUnknownVtbl {
+            QueryInterface: protocol_qi,
+            AddRef: protocol_add_ref,
+            Release: protocol_release,
+        },
+        Start: protocol_start,
+        Continue: protocol_continue,
+        Abort: protocol_abort,
+        Terminate: protocol_terminate,
+        Suspend: protocol_suspend,
+        Resume: protocol_resume,
+    },
+    Read: protocol_read,
+    Seek: protocol_seek,
+    LockRequest: protocol_lock_request,
+    UnlockRequest: protocol_unlock_request,
+};
+
+unsafe extern "system" fn protocol_qi(
+    _this: *mut IUnknown,
+    _riid: REFIID,
+    obj: *mut LPVOID,
+) -> HRESULT {
+    *obj = ptr::null_mut();
+    E_NOINTERFACE
+}
+
+unsafe extern "system" fn protocol_add_ref(this: *mut IUnknown) -> ULONG {
+    let this = &*(this as *const InternetProtocol);
+    this.refs.fetch_add(1, Ordering::SeqCst) + 1
+}
+
+unsafe extern "system" fn protocol_release(this: *mut IUnknown) -> ULONG {
+    let count = {
+        let this = &*(this as *const InternetProtocol);
+        this.refs.fetch_sub(1, Ordering::SeqCst) - 1
+    };
+    if count == 0 {
+        drop(Box::from_raw(this as *mut InternetProtocol));
+    }
+    count
+}
+
+unsafe extern "system" fn protocol_start(
+    this: *mut IInternetProtocol,
+    url: winapi::um::winnt::LPCWSTR,
+    sink: *mut IInternetProtocolSink,
+    _bind_info: *mut winapi::um::urlmon::IInternetBindInfo,
+    _flags: DWORD,
+    _reserved: DWORD,
+) -> HRESULT {
+    let this = &mut *(this as *mut InternetProtocol);
+    this.sink = sink;
+
+    let url = crate::mshtml::from_wstring(url);
+    let url = url.to_string_lossy().into_owned();
+
+    let scheme = match url.split_once("://") {
+        Some((scheme, _)) => scheme,
+        None => return winapi::shared::winerror::INET_E_INVALID_URL,
+    };
+
+    let registration = match (*this.webview).uri_schemes.get(scheme) {
+        Some(r) => r,
+        None => return winapi::shared::winerror::INET_E_INVALID_URL,
+    };
+
+    let request = Box::new(UriSchemeRequest {
+        webview: this.webview,
+        protocol: this as *mut InternetProtocol,
+        url: CString::new(url.clone()).unwrap(),
+    });
+
+    let handler = registration.handler;
+    let userdata = registration.userdata;
+    let url_cstr = request.url.clone();
+    handler(Box::into_raw(request), url_cstr.as_ptr(), userdata);
+
+    // The handler may already have responded synchronously, or may still
+    // be working asynchronously; either way `Read` below will see
+    // whatever landed in `this.buffer` once `ReportResult` fires.
+    S_OK
+}
+
+unsafe extern "system" fn protocol_continue(
+    _this: *mut IInternetProtocol,
+    _data: *mut PROTOCOLDATA,
+) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn protocol_abort(
+    _this: *mut IInternetProtocol,
+    _reason: HRESULT,
+    _options: DWORD,
+) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn protocol_terminate(_this: *mut IInternetProtocol, _options: DWORD) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn protocol_suspend(_this: *mut IInternetProtocol) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn protocol_resume(_this: *mut IInternetProtocol) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn protocol_read(
+    this: *mut IInternetProtocol,
+    buffer: LPVOID,
+    size: ULONG,
+    read: *mut ULONG,
+) -> HRESULT {
+    let this = &mut *(this as *mut InternetProtocol);
+    let remaining = this.buffer.len() - this.cursor;
+    if remaining == 0 {
+        *read = 0;
+        // `webview_uri_scheme_respond` hasn't run yet (it's always
+        // delivered asynchronously via `post_dispatch`) — tell the
+        // binding to call back later rather than reporting end-of-stream
+        // on a response that hasn't arrived.
+        return if this.finished {
+            S_FALSE
+        } else {
+            winapi::shared::winerror::E_PENDING
+        };
+    }
+
+    let n = (size as usize).min(remaining);
+    ptr::copy_nonoverlapping(this.buffer[this.cursor..].as_ptr(), buffer as *mut u8, n);
+    this.cursor += n;
+    *read = n as ULONG;
+
+    if this.cursor == this.buffer.len() {
+        S_FALSE
+    } else {
+        S_OK
+    }
+}
+
+unsafe extern "system" fn protocol_seek(
+    _this: *mut IInternetProtocol,
+    _move: winapi::um::winnt::LARGE_INTEGER,
+    _origin: DWORD,
+    _new_pos: *mut winapi::shared::ntdef::ULARGE_INTEGER,
+) -> HRESULT {
+    E_NOTIMPL
+}
+
+unsafe extern "system" fn protocol_lock_request(_this: *mut IInternetProtocol, _options: DWORD) -> HRESULT {
+    S_OK
+}
+
+unsafe extern "system" fn protocol_unlock_request(_this: *mut IInternetProtocol) -> HRESULT {
+    S_OK
+}
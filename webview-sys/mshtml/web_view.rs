@@ -0,0 +1,207 @@
+use std::cell::RefCell;
+use std::ptr;
+use std::rc::Rc;
+
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::combaseapi::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+use winapi::um::mshtml::{IHTMLDocument2, CLSID_HTMLDocument};
+use winapi::um::mshtmlc::IHTMLWindow2;
+use winapi::um::objidl::IPersistMoniker;
+use winapi::um::oleauto::SysAllocString;
+use winapi::um::oleidl::{IOleInPlaceObject, IOleObject};
+use winapi::um::urlmon::CreateURLMonikerW;
+use winapi::Interface;
+
+use crate::mshtml::engine::WebViewEngine;
+use crate::mshtml::interface::{ClientSite, InvokeState};
+use crate::mshtml::to_wstring;
+
+/// MSHTML-backed engine: hosts an `IHTMLDocument2` via a minimal
+/// `IOleClientSite`/`IOleInPlaceSite` pair (see `interface.rs`).
+pub(crate) struct WebView {
+    hwnd: HWND,
+    ole_object: *mut IOleObject,
+    document: *mut IHTMLDocument2,
+    window: *mut IHTMLWindow2,
+    /// Owned solely through its own `IUnknown` refcount (see
+    /// `client_site_release`) once handed to MSHTML below, not through a
+    /// Rust `Box` here too — keeping a `Box` here as well as letting COM
+    /// free it on the final `Release` would double-free it.
+    client_site: *mut ClientSite,
+    state: Rc<RefCell<InvokeState>>,
+}
+
+impl WebView {
+    pub(crate) fn new() -> Box<WebView> {
+        Box::new(WebView {
+            hwnd: ptr::null_mut(),
+            ole_object: ptr::null_mut(),
+            document: ptr::null_mut(),
+            window: ptr::null_mut(),
+            client_site: ptr::null_mut(),
+            state: Rc::new(RefCell::new(InvokeState { callback: None })),
+        })
+    }
+
+    pub(crate) fn initialize(&mut self, parent: HWND, rect: RECT) {
+        self.hwnd = parent;
+
+        unsafe {
+            let mut document: *mut IHTMLDocument2 = ptr::null_mut();
+            let hr = CoCreateInstance(
+                &CLSID_HTMLDocument,
+                ptr::null_mut(),
+                CLSCTX_INPROC_SERVER,
+                &IHTMLDocument2::uuidof(),
+                &mut document as *mut _ as *mut _,
+            );
+            if hr < 0 || document.is_null() {
+                eprintln!("could not create HTMLDocument, hr={:#x}", hr);
+                return;
+            }
+            self.document = document;
+
+            let mut ole_object: *mut IOleObject = ptr::null_mut();
+            (*document).QueryInterface(&IOleObject::uuidof(), &mut ole_object as *mut _ as *mut _);
+            self.ole_object = ole_object;
+
+            let client_site = ClientSite::new(parent, self.state.clone());
+            let site_ptr = client_site.as_ole_client_site();
+            self.client_site = Box::into_raw(client_site);
+
+            (*ole_object).SetClientSite(site_ptr);
+            (*ole_object).DoVerb(
+                winapi::um::oleidl::OLEIVERB_SHOW,
+                ptr::null_mut(),
+                site_ptr,
+                0,
+                parent,
+                &rect,
+            );
+
+            let mut window: *mut IHTMLWindow2 = ptr::null_mut();
+            (*document).get_parentWindow(&mut window);
+            self.window = window;
+        }
+    }
+}
+
+impl WebViewEngine for WebView {
+    fn navigate(&self, url: &str) {
+        unsafe {
+            if self.document.is_null() {
+                return;
+            }
+
+            let mut persist_moniker: *mut IPersistMoniker = ptr::null_mut();
+            (*self.document).QueryInterface(
+                &IPersistMoniker::uuidof(),
+                &mut persist_moniker as *mut _ as *mut _,
+            );
+            if persist_moniker.is_null() {
+                eprintln!("document does not implement IPersistMoniker");
+                return;
+            }
+
+            let wide_url = to_wstring(url);
+            let mut moniker = ptr::null_mut();
+            let hr = CreateURLMonikerW(ptr::null_mut(), wide_url.as_ptr(), &mut moniker);
+            if hr < 0 || moniker.is_null() {
+                eprintln!("CreateURLMonikerW({}) failed, hr={:#x}", url, hr);
+                (*persist_moniker).Release();
+                return;
+            }
+
+            (*persist_moniker).Load(0, moniker, ptr::null_mut(), 0);
+            (*moniker).Release();
+            (*persist_moniker).Release();
+        }
+    }
+
+    fn eval(&self, js: &str) {
+        unsafe {
+            if self.window.is_null() {
+                return;
+            }
+
+            let script = SysAllocString(to_wstring(js).as_ptr());
+            let language = SysAllocString(to_wstring("JScript").as_ptr());
+            let mut result = std::mem::zeroed();
+            (*self.window).execScript(script, language, &mut result);
+        }
+    }
+
+    fn write(&self, html: &str) {
+        unsafe {
+            if self.document.is_null() {
+                return;
+            }
+
+            (*self.document).close();
+            (*self.document).open(
+                SysAllocString(to_wstring("text/html").as_ptr()),
+                std::mem::zeroed(),
+                std::mem::zeroed(),
+                std::mem::zeroed(),
+                ptr::null_mut(),
+            );
+
+            let bstr_html = SysAllocString(to_wstring(html).as_ptr());
+            let mut variant: winapi::um::oaidl::VARIANT = std::mem::zeroed();
+            (*variant.n1.n2_mut()).vt = winapi::shared::wtypes::VT_BSTR as u16;
+            *(*variant.n1.n2_mut()).n3.bstrVal_mut() = bstr_html;
+
+            let array = winapi::um::oleauto::SafeArrayCreateVector(
+                winapi::shared::wtypes::VT_VARIANT as u16,
+                0,
+                1,
+            );
+            if !array.is_null() {
+                let mut index = 0i32;
+                winapi::um::oleauto::SafeArrayPutElement(
+                    array,
+                    &mut index,
+                    &mut variant as *mut _ as *mut _,
+                );
+                (*self.document).write(array);
+                winapi::um::oleauto::SafeArrayDestroy(array);
+            }
+
+            (*self.document).close();
+        }
+    }
+
+    fn set_callback(&mut self, cb: Option<Box<dyn Fn(String)>>) {
+        self.state.borrow_mut().callback = cb;
+    }
+
+    fn resize(&self, rect: RECT) {
+        unsafe {
+            if self.ole_object.is_null() {
+                return;
+            }
+
+            let mut in_place: *mut IOleInPlaceObject = ptr::null_mut();
+            (*self.ole_object).QueryInterface(
+                &IOleInPlaceObject::uuidof(),
+                &mut in_place as *mut _ as *mut _,
+            );
+            if in_place.is_null() {
+                return;
+            }
+
+            (*in_place).SetObjectRects(&rect, &rect);
+            (*in_place).Release();
+        }
+    }
+
+    fn persist_script(&mut self, _script: &str) -> bool {
+        // MSHTML has no `AddScriptToExecuteOnDocumentCreated` equivalent
+        // and this crate doesn't hook `DWebBrowserEvents2::DocumentComplete`,
+        // so there's no way to re-run `_script` on a page-initiated
+        // navigation; tell the caller so it can treat bindings as
+        // current-document-only instead of claiming persistence it can't
+        // deliver.
+        false
+    }
+}
@@ -0,0 +1,190 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc;
+
+use webview2_com::Microsoft::Web::WebView2::Win32::{
+    CreateCoreWebView2EnvironmentWithOptions, ICoreWebView2, ICoreWebView2Controller,
+};
+use webview2_com::{
+    AddScriptToExecuteOnDocumentCreatedCompletedHandler, CreateCoreWebView2ControllerCompletedHandler,
+    CreateCoreWebView2EnvironmentCompletedHandler, ExecuteScriptCompletedHandler,
+};
+use windows::core::{HSTRING, PWSTR};
+use winapi::shared::windef::{HWND, RECT};
+use winapi::um::winuser::{DispatchMessageW, PeekMessageW, TranslateMessage, MSG, PM_REMOVE};
+
+use crate::mshtml::engine::WebViewEngine;
+
+/// WebView2 (Edge/Chromium) backend: a `CoreWebView2Controller` hosted in
+/// the same top-level `HWND` the MSHTML backend would otherwise use, so
+/// `webview_loop`/`webview_dispatch`/`webview_eval` drive it identically.
+pub(crate) struct WebView2 {
+    hwnd: HWND,
+    controller: Option<ICoreWebView2Controller>,
+    webview: Option<ICoreWebView2>,
+    callback: Rc<RefCell<Option<Box<dyn Fn(String)>>>>,
+}
+
+impl WebView2 {
+    pub(crate) fn new() -> Box<WebView2> {
+        Box::new(WebView2 {
+            hwnd: std::ptr::null_mut(),
+            controller: None,
+            webview: None,
+            callback: Rc::new(RefCell::new(None)),
+        })
+    }
+
+    /// Blocks until the environment and controller are created, since the
+    /// rest of `webview_new_webview2` expects a usable engine on return.
+    /// `CreateCoreWebView2EnvironmentWithOptions`/`CreateCoreWebView2Controller`
+    /// deliver their completion callbacks on this thread's STA message
+    /// queue, and `webview_loop` hasn't started yet at this point in
+    /// `webview_new_webview2` — so this pumps the queue itself
+    /// (`PeekMessageW`/`DispatchMessageW`) until the controller channel
+    /// has a result, instead of relying on a loop that doesn't exist yet.
+    pub(crate) fn initialize(&mut self, hwnd: HWND, rect: RECT) {
+        self.hwnd = hwnd;
+
+        let (tx, rx) = mpsc::channel();
+        let hwnd_copy = hwnd as isize;
+
+        let result = unsafe {
+            CreateCoreWebView2EnvironmentWithOptions(
+                PWSTR::null(),
+                PWSTR::null(),
+                None,
+                CreateCoreWebView2EnvironmentCompletedHandler::create(Box::new(
+                    move |_err, environment| {
+                        let environment = environment.expect("environment");
+                        let hwnd = hwnd_copy as HWND;
+                        environment
+                            .CreateCoreWebView2Controller(
+                                hwnd as _,
+                                CreateCoreWebView2ControllerCompletedHandler::create(Box::new(
+                                    move |_err, controller| {
+                                        let _ = tx.send(controller);
+                                        Ok(())
+                                    },
+                                )),
+                            )
+                            .expect("CreateCoreWebView2Controller");
+                        Ok(())
+                    },
+                )),
+            )
+        };
+
+        if result.is_err() {
+            eprintln!("CreateCoreWebView2EnvironmentWithOptions failed: {:?}", result);
+            return;
+        }
+
+        let controller = loop {
+            match rx.try_recv() {
+                Ok(Some(controller)) => break controller,
+                Ok(None) | Err(mpsc::TryRecvError::Disconnected) => {
+                    eprintln!("WebView2 controller creation failed");
+                    return;
+                }
+                Err(mpsc::TryRecvError::Empty) => unsafe {
+                    let mut msg: MSG = std::mem::zeroed();
+                    if PeekMessageW(&mut msg, std::ptr::null_mut(), 0, 0, PM_REMOVE) != 0 {
+                        TranslateMessage(&msg);
+                        DispatchMessageW(&msg);
+                    }
+                },
+            }
+        };
+
+        unsafe {
+            let _ = controller.SetBounds(RECT {
+                left: rect.left,
+                top: rect.top,
+                right: rect.right,
+                bottom: rect.bottom,
+            });
+            let _ = controller.SetIsVisible(true);
+
+            if let Ok(webview) = controller.CoreWebView2() {
+                let callback = self.callback.clone();
+                let _ = webview.add_WebMessageReceived(
+                    &webview2_com::WebMessageReceivedEventHandler::create(Box::new(
+                        move |_webview, args| {
+                            if let Some(args) = args {
+                                let mut message = PWSTR::null();
+                                if args.TryGetWebMessageAsString(&mut message).is_ok() {
+                                    let message = message.to_string().unwrap_or_default();
+                                    if let Some(cb) = callback.borrow().as_ref() {
+                                        cb(message);
+                                    }
+                                }
+                            }
+                            Ok(())
+                        },
+                    )),
+                    std::ptr::null_mut(),
+                );
+                self.webview = Some(webview);
+            }
+        }
+
+        self.controller = Some(controller);
+    }
+}
+
+impl WebViewEngine for WebView2 {
+    fn navigate(&self, url: &str) {
+        if let Some(webview) = &self.webview {
+            unsafe {
+                let _ = webview.Navigate(&HSTRING::from(url));
+            }
+        }
+    }
+
+    fn eval(&self, js: &str) {
+        if let Some(webview) = &self.webview {
+            unsafe {
+                let _ = webview.ExecuteScript(
+                    &HSTRING::from(js),
+                    &ExecuteScriptCompletedHandler::create(Box::new(|_err, _result| Ok(()))),
+                );
+            }
+        }
+    }
+
+    fn write(&self, html: &str) {
+        if let Some(webview) = &self.webview {
+            unsafe {
+                let _ = webview.NavigateToString(&HSTRING::from(html));
+            }
+        }
+    }
+
+    fn set_callback(&mut self, cb: Option<Box<dyn Fn(String)>>) {
+        *self.callback.borrow_mut() = cb;
+    }
+
+    fn resize(&self, rect: RECT) {
+        if let Some(controller) = &self.controller {
+            unsafe {
+                let _ = controller.SetBounds(rect);
+            }
+        }
+    }
+
+    fn persist_script(&mut self, script: &str) -> bool {
+        if let Some(webview) = &self.webview {
+            unsafe {
+                let result = webview.AddScriptToExecuteOnDocumentCreated(
+                    &HSTRING::from(script),
+                    &AddScriptToExecuteOnDocumentCreatedCompletedHandler::create(Box::new(
+                        |_err, _id| Ok(()),
+                    )),
+                );
+                return result.is_ok();
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,398 @@
+use libc::c_void;
+use std::ptr;
+use winapi::shared::minwindef::{LPARAM, LRESULT, UINT, WPARAM};
+use winapi::shared::windef::{HBRUSH, HWND, RECT};
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::wingdi::{CreateSolidBrush, DeleteObject, RGB};
+use winapi::um::winuser::*;
+
+use crate::mshtml::to_wstring;
+use crate::mshtml::CWebView;
+use crate::mshtml::ErasedDispatchFn;
+
+pub(crate) const WM_WEBVIEW_DISPATCH: UINT = WM_APP + 1;
+
+const CLASS_NAME: &str = "webview";
+
+pub(crate) struct DispatchData {
+    pub(crate) target: *mut CWebView,
+    pub(crate) func: ErasedDispatchFn,
+    pub(crate) arg: *mut c_void,
+}
+
+/// `webview_set_size`'s hint modes, matching the GTK/zserge webview
+/// surface: `None` lets the window resize freely, `Min`/`Max` set a
+/// bound enforced on `WM_GETMINMAXINFO`, `Fixed` does both (locks size
+/// and also drops `WS_THICKFRAME`/`WS_MAXIMIZEBOX`).
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SizeHint {
+    None = 0,
+    Min = 1,
+    Max = 2,
+    Fixed = 3,
+}
+
+/// Per-window state that `wndproc` needs but that doesn't fit in the
+/// `HWND` itself, stashed behind `GWLP_USERDATA`.
+struct WindowState {
+    min_size: Option<(i32, i32)>,
+    max_size: Option<(i32, i32)>,
+    bg_brush: HBRUSH,
+    saved_placement: WINDOWPLACEMENT,
+    fullscreen: bool,
+    /// Invoked with the new client `RECT` whenever the top-level window
+    /// is resized/rescaled, so the embedded engine (MSHTML's `IOleObject`
+    /// or WebView2's `ICoreWebView2Controller`) can keep its hosted
+    /// content's bounds in sync, including across `WM_DPICHANGED`.
+    on_resize: Option<Box<dyn Fn(RECT)>>,
+    /// Whether this `HWND` was created without a parent (`Window::new`)
+    /// rather than embedded into a host's window (`Window::new_embedded`).
+    /// `wndproc` only calls `PostQuitMessage` for a top-level window's
+    /// `WM_DESTROY`, since `PostQuitMessage` posts `WM_QUIT` to the
+    /// calling thread's queue regardless of which `HWND` is being
+    /// destroyed, and an embedded control's teardown must not quit the
+    /// host application's message loop.
+    top_level: bool,
+}
+
+impl Default for WindowState {
+    fn default() -> Self {
+        WindowState {
+            min_size: None,
+            max_size: None,
+            bg_brush: ptr::null_mut(),
+            saved_placement: unsafe { std::mem::zeroed() },
+            fullscreen: false,
+            on_resize: None,
+            top_level: true,
+        }
+    }
+}
+
+pub(crate) struct Window {
+    hwnd: HWND,
+}
+
+impl Window {
+    pub(crate) fn new() -> Window {
+        Window::create(ptr::null_mut(), None)
+    }
+
+    /// Hosts the webview as a `WS_CHILD` of `parent` sized to `rect`
+    /// instead of creating its own top-level window, so native apps
+    /// (FLTK/win32 hosts, etc.) can embed it inside an existing window.
+    pub(crate) fn new_embedded(parent: HWND, rect: RECT) -> Window {
+        Window::create(parent, Some(rect))
+    }
+
+    fn create(parent: HWND, rect: Option<RECT>) -> Window {
+        unsafe {
+            let hinstance = GetModuleHandleW(ptr::null());
+            let class_name = to_wstring(CLASS_NAME);
+
+            let wnd_class = WNDCLASSW {
+                style: CS_HREDRAW | CS_VREDRAW,
+                lpfnWndProc: Some(wndproc),
+                hInstance: hinstance,
+                lpszClassName: class_name.as_ptr(),
+                cbClsExtra: 0,
+                cbWndExtra: 0,
+                hIcon: ptr::null_mut(),
+                hCursor: LoadCursorW(ptr::null_mut(), IDC_ARROW),
+                hbrBackground: ptr::null_mut(),
+                lpszMenuName: ptr::null(),
+            };
+
+            RegisterClassW(&wnd_class);
+
+            let (style, x, y, width, height) = if parent.is_null() {
+                (
+                    WS_OVERLAPPEDWINDOW,
+                    CW_USEDEFAULT,
+                    CW_USEDEFAULT,
+                    CW_USEDEFAULT,
+                    CW_USEDEFAULT,
+                )
+            } else {
+                let rect = rect.expect("rect is required when embedding into a parent HWND");
+                (
+                    WS_CHILD | WS_VISIBLE,
+                    rect.left,
+                    rect.top,
+                    rect.right - rect.left,
+                    rect.bottom - rect.top,
+                )
+            };
+
+            let hwnd = CreateWindowExW(
+                0,
+                class_name.as_ptr(),
+                to_wstring("").as_ptr(),
+                style,
+                x,
+                y,
+                width,
+                height,
+                parent,
+                ptr::null_mut(),
+                hinstance,
+                ptr::null_mut(),
+            );
+
+            let mut state = Box::new(WindowState::default());
+            state.top_level = parent.is_null();
+            SetWindowLongPtrW(hwnd, GWLP_USERDATA, Box::into_raw(state) as _);
+
+            Window { hwnd }
+        }
+    }
+
+    pub(crate) fn handle(&self) -> HWND {
+        self.hwnd
+    }
+
+    /// Repositions the window (top-level or embedded child) to `rect`
+    /// and notifies the hosted engine so it can follow along; used by
+    /// `webview_resize` when a host forwards its own resize events to an
+    /// embedded webview.
+    pub(crate) fn resize(&self, rect: RECT) {
+        unsafe {
+            SetWindowPos(
+                self.hwnd,
+                ptr::null_mut(),
+                rect.left,
+                rect.top,
+                rect.right - rect.left,
+                rect.bottom - rect.top,
+                SWP_NOZORDER | SWP_NOACTIVATE,
+            );
+
+            if let Some(on_resize) = &self.state().on_resize {
+                let mut client_rect: RECT = std::mem::zeroed();
+                GetClientRect(self.hwnd, &mut client_rect);
+                on_resize(client_rect);
+            }
+        }
+    }
+
+    unsafe fn state(&self) -> &mut WindowState {
+        &mut *(GetWindowLongPtrW(self.hwnd, GWLP_USERDATA) as *mut WindowState)
+    }
+
+    pub(crate) fn set_title(&self, title: &str) {
+        unsafe {
+            SetWindowTextW(self.hwnd, to_wstring(title).as_ptr());
+        }
+    }
+
+    pub(crate) fn set_fullscreen(&self, fullscreen: bool) {
+        unsafe {
+            let state = self.state();
+            if fullscreen == state.fullscreen {
+                return;
+            }
+
+            if fullscreen {
+                let mut placement: WINDOWPLACEMENT = std::mem::zeroed();
+                placement.length = std::mem::size_of::<WINDOWPLACEMENT>() as u32;
+                GetWindowPlacement(self.hwnd, &mut placement);
+                state.saved_placement = placement;
+
+                let mut monitor_info: MONITORINFO = std::mem::zeroed();
+                monitor_info.cbSize = std::mem::size_of::<MONITORINFO>() as u32;
+                let monitor = MonitorFromWindow(self.hwnd, MONITOR_DEFAULTTONEAREST);
+                GetMonitorInfoW(monitor, &mut monitor_info);
+
+                SetWindowLongPtrW(
+                    self.hwnd,
+                    GWL_STYLE,
+                    (GetWindowLongPtrW(self.hwnd, GWL_STYLE) as u32 & !WS_OVERLAPPEDWINDOW) as _,
+                );
+                let rc = monitor_info.rcMonitor;
+                SetWindowPos(
+                    self.hwnd,
+                    ptr::null_mut(),
+                    rc.left,
+                    rc.top,
+                    rc.right - rc.left,
+                    rc.bottom - rc.top,
+                    SWP_NOZORDER | SWP_NOACTIVATE | SWP_FRAMECHANGED,
+                );
+            } else {
+                SetWindowLongPtrW(
+                    self.hwnd,
+                    GWL_STYLE,
+                    (GetWindowLongPtrW(self.hwnd, GWL_STYLE) as u32 | WS_OVERLAPPEDWINDOW) as _,
+                );
+                SetWindowPlacement(self.hwnd, &state.saved_placement);
+                SetWindowPos(
+                    self.hwnd,
+                    ptr::null_mut(),
+                    0,
+                    0,
+                    0,
+                    0,
+                    SWP_NOMOVE | SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE | SWP_FRAMECHANGED,
+                );
+            }
+
+            state.fullscreen = fullscreen;
+        }
+    }
+
+    pub(crate) fn set_size(&self, width: i32, height: i32, hint: SizeHint) {
+        unsafe {
+            let state = self.state();
+
+            match hint {
+                SizeHint::Min => state.min_size = Some((width, height)),
+                SizeHint::Max => state.max_size = Some((width, height)),
+                SizeHint::Fixed => {
+                    state.min_size = Some((width, height));
+                    state.max_size = Some((width, height));
+                }
+                SizeHint::None => {
+                    state.min_size = None;
+                    state.max_size = None;
+                }
+            }
+
+            if hint == SizeHint::Fixed {
+                let style = GetWindowLongPtrW(self.hwnd, GWL_STYLE) as u32
+                    & !(WS_THICKFRAME | WS_MAXIMIZEBOX);
+                SetWindowLongPtrW(self.hwnd, GWL_STYLE, style as _);
+            } else if hint == SizeHint::None && state.top_level {
+                let style = GetWindowLongPtrW(self.hwnd, GWL_STYLE) as u32
+                    | WS_THICKFRAME
+                    | WS_MAXIMIZEBOX;
+                SetWindowLongPtrW(self.hwnd, GWL_STYLE, style as _);
+            }
+
+            if hint != SizeHint::Min && hint != SizeHint::Max {
+                SetWindowPos(
+                    self.hwnd,
+                    ptr::null_mut(),
+                    0,
+                    0,
+                    width,
+                    height,
+                    SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE | SWP_FRAMECHANGED,
+                );
+            }
+        }
+    }
+
+    /// Registers the callback `WM_DPICHANGED` (and any other resize)
+    /// uses to keep the hosted engine's bounds in sync with the window.
+    pub(crate) fn set_resize_handler(&self, cb: Box<dyn Fn(RECT)>) {
+        unsafe {
+            self.state().on_resize = Some(cb);
+        }
+    }
+
+    pub(crate) fn set_color(&self, r: u8, g: u8, b: u8) {
+        unsafe {
+            let state = self.state();
+            if !state.bg_brush.is_null() {
+                DeleteObject(state.bg_brush as _);
+            }
+            state.bg_brush = CreateSolidBrush(RGB(r.into(), g.into(), b.into()));
+            InvalidateRect(self.hwnd, ptr::null(), 1);
+        }
+    }
+}
+
+unsafe extern "system" fn wndproc(
+    hwnd: HWND,
+    msg: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match msg {
+        WM_WEBVIEW_DISPATCH => {
+            let data = Box::from_raw(lparam as *mut DispatchData);
+            (data.func)(data.target, data.arg);
+            0
+        }
+        WM_GETMINMAXINFO => {
+            let state_ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WindowState;
+            if !state_ptr.is_null() {
+                let state = &*state_ptr;
+                let info = &mut *(lparam as *mut MINMAXINFO);
+                if let Some((w, h)) = state.min_size {
+                    info.ptMinTrackSize.x = w;
+                    info.ptMinTrackSize.y = h;
+                }
+                if let Some((w, h)) = state.max_size {
+                    info.ptMaxTrackSize.x = w;
+                    info.ptMaxTrackSize.y = h;
+                }
+            }
+            0
+        }
+        WM_SIZE => {
+            let state_ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WindowState;
+            if !state_ptr.is_null() {
+                if let Some(on_resize) = &(*state_ptr).on_resize {
+                    let mut client_rect: RECT = std::mem::zeroed();
+                    GetClientRect(hwnd, &mut client_rect);
+                    on_resize(client_rect);
+                }
+            }
+            0
+        }
+        WM_DPICHANGED => {
+            // `lParam` points at the `RECT` Windows suggests for the new
+            // DPI; honoring it (rather than keeping the old pixel size)
+            // is what keeps content crisp when dragged to another
+            // monitor with different scaling.
+            let suggested = &*(lparam as *const RECT);
+            SetWindowPos(
+                hwnd,
+                ptr::null_mut(),
+                suggested.left,
+                suggested.top,
+                suggested.right - suggested.left,
+                suggested.bottom - suggested.top,
+                SWP_NOZORDER | SWP_NOACTIVATE,
+            );
+
+            let state_ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WindowState;
+            if !state_ptr.is_null() {
+                if let Some(on_resize) = &(*state_ptr).on_resize {
+                    let mut client_rect: RECT = std::mem::zeroed();
+                    GetClientRect(hwnd, &mut client_rect);
+                    on_resize(client_rect);
+                }
+            }
+            0
+        }
+        WM_ERASEBKGND => {
+            let state_ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WindowState;
+            if !state_ptr.is_null() && !(*state_ptr).bg_brush.is_null() {
+                let mut rect: RECT = std::mem::zeroed();
+                GetClientRect(hwnd, &mut rect);
+                FillRect(wparam as _, &rect, (*state_ptr).bg_brush);
+                return 1;
+            }
+            DefWindowProcW(hwnd, msg, wparam, lparam)
+        }
+        WM_DESTROY => {
+            let state_ptr = GetWindowLongPtrW(hwnd, GWLP_USERDATA) as *mut WindowState;
+            let mut top_level = true;
+            if !state_ptr.is_null() {
+                let state = Box::from_raw(state_ptr);
+                if !state.bg_brush.is_null() {
+                    DeleteObject(state.bg_brush as _);
+                }
+                top_level = state.top_level;
+            }
+            if top_level {
+                PostQuitMessage(0);
+            }
+            0
+        }
+        _ => DefWindowProcW(hwnd, msg, wparam, lparam),
+    }
+}